@@ -8,8 +8,8 @@ use core::ops::Add;
 
 use blockchain_sdk::storage::Persistent;
 use blockchain_sdk::{
-    contract, contracterror, contractimpl, contracttype, map, panic_with_error, token, vec,
-    Address, Env, Map, Symbol, Vec
+    contract, contracterror, contractimpl, contracttype, map, panic_with_error, symbol_short,
+    token, vec, Address, Env, Map, Symbol, Vec
 };
 
 /// State of the royalties distribution
@@ -31,6 +31,50 @@ enum DataKey {
     RoyaltiesState = 4,
     RoyaltiesPool = 5,
     Token = 6,
+    PlayCounts = 7,
+    FeeBps = 8,
+    Treasury = 9,
+    Splits = 10,
+    Claimable = 11,
+    History = 12,
+    TtlFloor = 13,
+}
+
+/// Default floor (in ledgers) below which a persistent entry's TTL is bumped.
+const DEFAULT_TTL_FLOOR: u32 = 17280;
+
+/// Default number of ledgers a bumped persistent entry is extended to live.
+const DEFAULT_TTL_BUMP: u32 = 518400;
+
+/// Extends the TTL of a long-lived persistent entry, but only once its
+/// remaining lifetime drops below the configured floor, so routine calls
+/// don't pay for extension on every access.
+fn extend_ttl(env: &Env, key: &DataKey) {
+    let storage = env.storage().persistent();
+    let floor = storage
+        .get::<_, u32>(&DataKey::TtlFloor)
+        .unwrap_or(DEFAULT_TTL_FLOOR);
+    storage.extend_ttl(key, floor, DEFAULT_TTL_BUMP);
+}
+
+/// A configured rightsholder split for an artist: parallel lists of payee
+/// addresses and their basis-point shares, which must sum to 10000.
+#[contracttype]
+#[derive(Clone)]
+pub struct Split {
+    pub recipients: Vec<Address>,
+    pub shares_bps: Vec<u32>,
+}
+
+/// A record of a single royalties distribution round, kept in an append-only
+/// history so off-chain dashboards can reconstruct the payout ledger.
+#[contracttype]
+#[derive(Clone)]
+pub struct DistributionRecord {
+    pub total_pool: i128,
+    pub fee: i128,
+    pub artist_count: u32,
+    pub timestamp: u64,
 }
 
 /// All errors this contract expects.
@@ -46,6 +90,8 @@ pub enum Error {
     InvalidArtistAddress = 6,
     InvalidListenerAddress = 7,
     InvalidRoyaltiesPercentage = 8,
+    InvalidFeePercentage = 9,
+    InvalidSplit = 10,
 }
 
 #[contract]
@@ -60,7 +106,9 @@ impl RoyaltiesContract {
     /// - env - The environment for this contract.
     /// - admin - Admin account address.
     /// - token - The token contract address used for royalties payments.
-    pub fn init(env: Env, admin: Address, token: Address) {
+    /// - fee_bps - Platform fee in basis points (0–10000) skimmed off each distribution.
+    /// - treasury - Address receiving the platform fee.
+    pub fn init(env: Env, admin: Address, token: Address, fee_bps: u32, treasury: Address) {
         admin.require_auth();
         let storage = env.storage().persistent();
         if storage
@@ -70,11 +118,70 @@ impl RoyaltiesContract {
             panic_with_error!(&env, Error::AlreadyInitialized);
         }
 
+        if fee_bps > 10000 {
+            panic_with_error!(&env, Error::InvalidFeePercentage);
+        }
+
         storage.set(&DataKey::Admin, &admin);
         storage.set(&DataKey::Token, &token);
+        storage.set(&DataKey::FeeBps, &fee_bps);
+        storage.set(&DataKey::Treasury, &treasury);
         storage.set(&DataKey::RoyaltiesState, &RoyaltiesState::Initialized);
     }
 
+    /// Updates the platform fee in basis points.
+    ///
+    /// # Arguments
+    ///
+    /// - `env` - The environment for this contract.
+    /// - `fee_bps` - New platform fee in basis points (0–10000).
+    pub fn set_fee_bps(env: Env, fee_bps: u32) {
+        let storage = env.storage().persistent();
+        let admin = storage.get::<_, Address>(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        if fee_bps > 10000 {
+            panic_with_error!(&env, Error::InvalidFeePercentage);
+        }
+
+        storage.set(&DataKey::FeeBps, &fee_bps);
+    }
+
+    /// Configures the rightsholder split for an artist.
+    ///
+    /// When set, the artist's royalty payout is fanned out across `recipients`
+    /// proportionally to `shares_bps`, which must sum to exactly 10000.
+    ///
+    /// # Arguments
+    ///
+    /// - `env` - The environment for this contract.
+    /// - `artist` - Artist's account address the split belongs to.
+    /// - `recipients` - Payee addresses sharing the artist's payout.
+    /// - `shares_bps` - Basis-point share per recipient, summing to 10000.
+    pub fn set_splits(env: Env, artist: Address, recipients: Vec<Address>, shares_bps: Vec<u32>) {
+        let storage = env.storage().persistent();
+        let admin = storage.get::<_, Address>(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        if recipients.len() != shares_bps.len() {
+            panic_with_error!(&env, Error::InvalidSplit);
+        }
+
+        let mut total_bps = 0u32;
+        for share in shares_bps.iter() {
+            total_bps += share;
+        }
+        if total_bps != 10000 {
+            panic_with_error!(&env, Error::InvalidSplit);
+        }
+
+        let mut splits = storage
+            .get::<_, Map<Address, Split>>(&DataKey::Splits)
+            .unwrap_or(map![&env]);
+        splits.set(artist, Split { recipients, shares_bps });
+        storage.set(&DataKey::Splits, &splits);
+    }
+
     /// Adds an artist to the platform.
     ///
     /// # Arguments
@@ -107,6 +214,56 @@ impl RoyaltiesContract {
         storage.set(&DataKey::Listeners, &listeners);
     }
 
+    /// Sets the TTL floor below which routine calls extend a persistent entry.
+    ///
+    /// # Arguments
+    ///
+    /// - `env` - The environment for this contract.
+    /// - `floor` - Remaining-TTL floor (in ledgers) that triggers an extension.
+    pub fn set_ttl_floor(env: Env, floor: u32) {
+        let storage = env.storage().persistent();
+        let admin = storage.get::<_, Address>(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        storage.set(&DataKey::TtlFloor, &floor);
+    }
+
+    /// Extends the TTL of every long-lived persistent entry.
+    ///
+    /// Lets a platform operator keep royalty state alive between distribution
+    /// rounds so an in-progress cycle is never silently archived. Every entry
+    /// is extended to live `ledgers_to_live` ledgers from now.
+    ///
+    /// # Arguments
+    ///
+    /// - `env` - The environment for this contract.
+    /// - `ledgers_to_live` - Number of ledgers each entry is extended to live.
+    pub fn bump_ttl(env: Env, ledgers_to_live: u32) {
+        let storage = env.storage().persistent();
+        let admin = storage.get::<_, Address>(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        let keys = [
+            DataKey::Admin,
+            DataKey::Artists,
+            DataKey::Listeners,
+            DataKey::RoyaltiesState,
+            DataKey::RoyaltiesPool,
+            DataKey::Token,
+            DataKey::PlayCounts,
+            DataKey::FeeBps,
+            DataKey::Treasury,
+            DataKey::Splits,
+            DataKey::Claimable,
+            DataKey::History,
+        ];
+        // Use `ledgers_to_live` as both threshold and extend-to target so a
+        // modest value never trips the SDK's `extend_to >= threshold` rule.
+        for key in keys.iter() {
+            storage.extend_ttl(key, ledgers_to_live, ledgers_to_live);
+        }
+    }
+
     /// Starts the royalties distribution.
     ///
     /// # Arguments
@@ -149,8 +306,53 @@ impl RoyaltiesContract {
         storage.set(&DataKey::RoyaltiesState, &RoyaltiesState::Finished);
     }
 
+    /// Records a single play of an artist by a listener.
+    ///
+    /// Play counts are accumulated per artist and used to weight the next
+    /// royalties distribution, so artists who are streamed more earn more.
+    ///
+    /// # Arguments
+    ///
+    /// - `env` - The environment for this contract.
+    /// - `listener` - Listener's account address performing the play.
+    /// - `artist` - Artist's account address being played.
+    pub fn record_play(env: Env, listener: Address, artist: Address) {
+        listener.require_auth();
+        let storage = env.storage().persistent();
+
+        // Only registered listeners may play, and only registered artists may
+        // accrue weight, so play counts that drive payouts can't be forged.
+        let listeners = storage.get::<_, Vec<Address>>(&DataKey::Listeners).unwrap_or(vec![&env]);
+        if !listeners.contains(&listener) {
+            panic_with_error!(&env, Error::InvalidListenerAddress);
+        }
+        let artists = storage.get::<_, Vec<Address>>(&DataKey::Artists).unwrap_or(vec![&env]);
+        if !artists.contains(&artist) {
+            panic_with_error!(&env, Error::InvalidArtistAddress);
+        }
+
+        let mut play_counts = storage
+            .get::<_, Map<Address, i128>>(&DataKey::PlayCounts)
+            .unwrap_or(map![&env]);
+        let plays = play_counts.get(artist.clone()).unwrap_or(0);
+        play_counts.set(artist, plays + 1);
+        storage.set(&DataKey::PlayCounts, &play_counts);
+
+        extend_ttl(&env, &DataKey::PlayCounts);
+    }
+
     /// Distributes royalties to artists based on the royalties pool.
     ///
+    /// Rather than pushing transfers to every artist in a single transaction
+    /// (which exceeds the resource budget once the artist list grows), this
+    /// records each payee's owed balance into `DataKey::Claimable`, snapshotting
+    /// the pool at distribution time. Artists then withdraw independently via
+    /// [`claim`](Self::claim). The platform fee is transferred to the treasury
+    /// first; each artist's share of the rest is weighted by recorded play
+    /// count (`(pool - fee) * plays[artist] / total_plays`), falling back to an
+    /// equal split when no plays were recorded; the integer-division remainder
+    /// is carried forward in the pool.
+    ///
     /// # Arguments
     ///
     /// - `env` - The environment for this contract.
@@ -169,20 +371,126 @@ impl RoyaltiesContract {
         let token_client = token::Client::new(&env, &token);
 
         let artists = storage.get::<_, Vec<Address>>(&DataKey::Artists).unwrap();
-        let listeners = storage.get::<_, Vec<Address>>(&DataKey::Listeners).unwrap();
         let royalties_pool = storage.get::<_, i128>(&DataKey::RoyaltiesPool).unwrap_or(0);
+        let play_counts = storage
+            .get::<_, Map<Address, i128>>(&DataKey::PlayCounts)
+            .unwrap_or(map![&env]);
+        let splits = storage
+            .get::<_, Map<Address, Split>>(&DataKey::Splits)
+            .unwrap_or(map![&env]);
+
+        // Skim the platform fee off the top and send it to the treasury.
+        let fee_bps = storage.get::<_, u32>(&DataKey::FeeBps).unwrap_or(0);
+        let fee = royalties_pool * fee_bps as i128 / 10000;
+        if fee > 0 {
+            let treasury = storage.get::<_, Address>(&DataKey::Treasury).unwrap();
+            token_client.transfer(&env.current_contract_address(), &treasury, &fee);
+        }
+        let distributable = royalties_pool - fee;
 
-        // Calculate royalties per artist
-        let num_artists = artists.len() as i128;
-        let royalties_per_artist = royalties_pool / num_artists;
+        // Total plays across all artists weights the split.
+        let mut total_plays = 0i128;
+        for artist in artists.iter() {
+            total_plays += play_counts.get(artist).unwrap_or(0);
+        }
 
-        // Distribute royalties to artists
+        // Record owed balances instead of pushing transfers, so each payee can
+        // withdraw independently. This loop still visits every artist and split
+        // recipient, but makes no token transfer per payee.
+        let mut claimable = storage
+            .get::<_, Map<Address, i128>>(&DataKey::Claimable)
+            .unwrap_or(map![&env]);
+        let mut distributed = 0i128;
         for artist in artists.iter() {
-            token_client.transfer(&env.current_contract_address(), artist, &royalties_per_artist);
+            let payout = if total_plays == 0 {
+                // No plays recorded: fall back to an equal split.
+                distributable / artists.len() as i128
+            } else {
+                distributable * play_counts.get(artist.clone()).unwrap_or(0) / total_plays
+            };
+
+            // Credit the artist's configured rightsholders, or the artist
+            // directly when no split is configured.
+            match splits.get(artist.clone()) {
+                Some(split) => {
+                    for i in 0..split.recipients.len() {
+                        let recipient = split.recipients.get(i).unwrap();
+                        let share = split.shares_bps.get(i).unwrap();
+                        let amount = payout * share as i128 / 10000;
+                        let owed = claimable.get(recipient.clone()).unwrap_or(0);
+                        claimable.set(recipient, owed + amount);
+                        // Count only what was actually credited, so the split's
+                        // own rounding remainder stays in the carried-forward pool.
+                        distributed += amount;
+                    }
+                }
+                None => {
+                    let owed = claimable.get(artist.clone()).unwrap_or(0);
+                    claimable.set(artist.clone(), owed + payout);
+                    distributed += payout;
+                }
+            }
+            env.events()
+                .publish((symbol_short!("payout"), artist), payout);
         }
+        storage.set(&DataKey::Claimable, &claimable);
+
+        // Append this round to the on-chain distribution history.
+        let mut history = storage
+            .get::<_, Vec<DistributionRecord>>(&DataKey::History)
+            .unwrap_or(vec![&env]);
+        history.push_back(DistributionRecord {
+            total_pool: royalties_pool,
+            fee,
+            artist_count: artists.len(),
+            timestamp: env.ledger().timestamp(),
+        });
+        storage.set(&DataKey::History, &history);
+
+        // Carry the remainder forward in the pool instead of zeroing it.
+        storage.set(&DataKey::RoyaltiesPool, &(royalties_pool - fee - distributed));
+
+        // Reset play counts so the next cycle starts fresh.
+        let fresh_plays: Map<Address, i128> = map![&env];
+        storage.set(&DataKey::PlayCounts, &fresh_plays);
+
+        // Keep the long-lived distribution state alive past this round.
+        extend_ttl(&env, &DataKey::RoyaltiesPool);
+        extend_ttl(&env, &DataKey::Claimable);
+        extend_ttl(&env, &DataKey::History);
+        extend_ttl(&env, &DataKey::PlayCounts);
+    }
 
-        // Clear royalties pool
-        storage.set(&DataKey::RoyaltiesPool, &0);
+    /// Withdraws the caller's accrued royalties.
+    ///
+    /// Reads the amount recorded for `artist` in `DataKey::Claimable`,
+    /// transfers it, and zeroes the entry. Requires the artist's authorization.
+    ///
+    /// # Arguments
+    ///
+    /// - `env` - The environment for this contract.
+    /// - `artist` - Account withdrawing its accrued royalties.
+    pub fn claim(env: Env, artist: Address) {
+        artist.require_auth();
+        let storage = env.storage().persistent();
+
+        let mut claimable = storage
+            .get::<_, Map<Address, i128>>(&DataKey::Claimable)
+            .unwrap_or(map![&env]);
+        let owed = claimable.get(artist.clone()).unwrap_or(0);
+        if owed == 0 {
+            return;
+        }
+
+        let token = storage.get::<_, Address>(&DataKey::Token).unwrap();
+        let token_client = token::Client::new(&env, &token);
+        token_client.transfer(&env.current_contract_address(), &artist, &owed);
+
+        claimable.set(artist.clone(), 0);
+        storage.set(&DataKey::Claimable, &claimable);
+        extend_ttl(&env, &DataKey::Claimable);
+
+        env.events().publish((symbol_short!("claim"), artist), owed);
     }
 
     /// Allows listeners to contribute to the royalties pool.
@@ -200,5 +508,22 @@ impl RoyaltiesContract {
 
         let royalties_pool = storage.get::<_, i128>(&DataKey::RoyaltiesPool).unwrap_or(0);
         storage.set(&DataKey::RoyaltiesPool, &(royalties_pool + amount));
+        extend_ttl(&env, &DataKey::RoyaltiesPool);
+
+        let listener = env.current_account();
+        env.events()
+            .publish((symbol_short!("contrib"), listener), amount);
+    }
+
+    /// Returns the append-only history of distribution rounds.
+    ///
+    /// # Arguments
+    ///
+    /// - `env` - The environment for this contract.
+    pub fn get_history(env: Env) -> Vec<DistributionRecord> {
+        env.storage()
+            .persistent()
+            .get::<_, Vec<DistributionRecord>>(&DataKey::History)
+            .unwrap_or(vec![&env])
     }
 }